@@ -0,0 +1,53 @@
+/// A well-known security principal that doesn't need to be looked up by
+/// name, e.g. `Everyone` or `Authenticated Users`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WellKnownPrincipal {
+    Everyone,
+    AuthenticatedUsers,
+}
+
+/// The security principal an [`AclRule`] applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Principal {
+    WellKnown(WellKnownPrincipal),
+    /// An account or group name, e.g. `"DOMAIN\\user"`, resolved via
+    /// `LookupAccountNameW` when the rule is applied.
+    Account(String),
+}
+
+/// The kinds of access an [`AclRule`] can allow or deny. Maps onto a
+/// combination of Win32 `ACCESS_MASK` bits when the rule is applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessRight {
+    Delete,
+    Write,
+    Read,
+}
+
+/// A single per-principal access-control entry to apply to a folder's
+/// DACL, replacing the historical single hardcoded "deny Everyone"
+/// policy with an arbitrary, user-specified set of allow/deny rules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AclRule {
+    pub principal: Principal,
+    pub allow: bool,
+    pub access: Vec<AccessRight>,
+}
+
+impl AclRule {
+    pub fn deny(principal: Principal, access: Vec<AccessRight>) -> Self {
+        Self {
+            principal,
+            allow: false,
+            access,
+        }
+    }
+
+    pub fn allow(principal: Principal, access: Vec<AccessRight>) -> Self {
+        Self {
+            principal,
+            allow: true,
+            access,
+        }
+    }
+}
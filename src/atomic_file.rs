@@ -0,0 +1,56 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use crate::error::LockerError;
+
+/// Writes `contents` into `dir.join(file_name)` crash-safely: the data is
+/// written to a sibling temp file, flushed, then moved over the real file
+/// with `fs::rename` (an atomic replace on both Unix and Windows), so a
+/// reader only ever observes the old complete contents or the new complete
+/// contents, never a truncated in-progress write.
+pub fn write_atomic(dir: &Path, file_name: &str, contents: &str) -> Result<(), LockerError> {
+    let final_path = dir.join(file_name);
+    let temp_path = dir.join(format!("{}.tmp", file_name));
+
+    let mut file = fs::File::create(&temp_path).map_err(|e| LockerError::FileOperationFailed {
+        operation: format!("create temp {} file at", file_name),
+        path: temp_path.clone(),
+        error: e.to_string(),
+    })?;
+    file.write_all(contents.as_bytes())
+        .and_then(|_| file.sync_all())
+        .map_err(|e| LockerError::FileOperationFailed {
+            operation: format!("write temp {} file at", file_name),
+            path: temp_path.clone(),
+            error: e.to_string(),
+        })?;
+    drop(file);
+
+    fs::rename(&temp_path, &final_path).map_err(|e| LockerError::FileOperationFailed {
+        operation: format!("atomically replace {} file at", file_name),
+        path: final_path,
+        error: e.to_string(),
+    })
+}
+
+/// Reads back a file written by [`write_atomic`], returning a typed error
+/// rather than silently treating a missing or empty file as valid data.
+pub fn read_atomic(dir: &Path, file_name: &str) -> Result<String, LockerError> {
+    let path = dir.join(file_name);
+    let contents = fs::read_to_string(&path).map_err(|e| LockerError::FileOperationFailed {
+        operation: format!("read {} file at", file_name),
+        path: path.clone(),
+        error: e.to_string(),
+    })?;
+
+    if contents.trim().is_empty() {
+        return Err(LockerError::FileOperationFailed {
+            operation: format!("read {} file at", file_name),
+            path,
+            error: format!("{} file is empty or malformed", file_name),
+        });
+    }
+
+    Ok(contents)
+}
@@ -0,0 +1,127 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use colored::*;
+use log::warn;
+
+use crate::error::LockerError;
+use crate::permission_manager::PermissionManager;
+use crate::progress::bar;
+
+/// A folder whose permissions/ACLs leave it exposed.
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub path: PathBuf,
+    pub issue: String,
+}
+
+/// Walks `root` and reports (optionally fixing) folders whose
+/// permissions leave them exposed to other principals.
+pub fn audit(root: &Path, fix: bool) -> Result<Vec<Finding>, LockerError> {
+    let dirs = collect_dirs(root)?;
+    let pb = bar(dirs.len() as u64);
+    let mut findings = Vec::new();
+
+    for dir in &dirs {
+        pb.set_message(dir.display().to_string());
+
+        if let Some(issue) = inspect(dir)? {
+            if fix {
+                PermissionManager::harden(dir).map_err(|e| LockerError::FileOperationFailed {
+                    operation: "fix permissions for".to_string(),
+                    path: dir.clone(),
+                    error: e.to_string(),
+                })?;
+                println!("{} {}: {}", "[fixed]".green().bold(), dir.display(), issue);
+            } else {
+                warn!("Insecure permissions on {:?}: {}", dir, issue);
+                println!(
+                    "{} {}: {}",
+                    "[vulnerable]".red().bold(),
+                    dir.display(),
+                    issue
+                );
+            }
+            findings.push(Finding {
+                path: dir.clone(),
+                issue,
+            });
+        }
+        pb.inc(1);
+    }
+
+    pb.finish_with_message(format!("Audit complete: {} finding(s).", findings.len()));
+    Ok(findings)
+}
+
+fn collect_dirs(root: &Path) -> Result<Vec<PathBuf>, LockerError> {
+    let mut dirs = Vec::new();
+    let entries = fs::read_dir(root).map_err(|e| LockerError::FileOperationFailed {
+        operation: "read".to_string(),
+        path: root.to_path_buf(),
+        error: e.to_string(),
+    })?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| LockerError::FileOperationFailed {
+            operation: "read entry in".to_string(),
+            path: root.to_path_buf(),
+            error: e.to_string(),
+        })?;
+        let path = entry.path();
+        if path.is_dir() {
+            dirs.push(path.clone());
+            dirs.extend(collect_dirs(&path)?);
+        }
+    }
+
+    Ok(dirs)
+}
+
+#[cfg(unix)]
+fn inspect(dir: &Path) -> Result<Option<String>, LockerError> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = fs::metadata(dir)
+        .map_err(|e| LockerError::FileOperationFailed {
+            operation: "stat".to_string(),
+            path: dir.to_path_buf(),
+            error: e.to_string(),
+        })?
+        .permissions()
+        .mode();
+
+    let mut issues = Vec::new();
+    if mode & 0o020 != 0 {
+        issues.push("group-writable");
+    }
+    if mode & 0o002 != 0 {
+        issues.push("other-writable");
+    }
+    if mode & 0o004 != 0 {
+        issues.push("world-readable");
+    }
+
+    Ok((!issues.is_empty()).then(|| issues.join(", ")))
+}
+
+#[cfg(windows)]
+fn inspect(dir: &Path) -> Result<Option<String>, LockerError> {
+    let path_str = dir.to_str().ok_or(LockerError::InvalidFolderName)?;
+    let listing = PermissionManager::icacls(&[path_str]).map_err(|e| {
+        LockerError::FileOperationFailed {
+            operation: "inspect ACL for".to_string(),
+            path: dir.to_path_buf(),
+            error: e.to_string(),
+        }
+    })?;
+
+    let everyone_exposed = listing.lines().any(|line| {
+        line.contains("Everyone")
+            && ["(W)", "(M)", "(F)", "(DE)", "(DC)"]
+                .iter()
+                .any(|mask| line.contains(mask))
+    });
+
+    Ok(everyone_exposed.then(|| "Everyone (S-1-1-0) holds write/delete access".to_string()))
+}
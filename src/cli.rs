@@ -1,4 +1,6 @@
 use crate::{
+    audit::audit,
+    config::LockerConfig,
     error::LockerError,
     folder_operations::{lock_folder, unlock_folder},
 };
@@ -22,12 +24,29 @@ pub enum Action {
         /// Path to the folder to lock
         #[clap(value_parser)]
         folder: Option<PathBuf>,
+        /// Deny delete access to this account instead of the default
+        /// "Everyone" policy (Windows only). May be repeated.
+        #[clap(long = "deny")]
+        deny: Vec<String>,
     },
     /// Unlock the folder
     Unlock {
         /// Path to the folder to unlock
         #[clap(value_parser)]
         folder: Option<PathBuf>,
+        /// Permanently delete the locked folder instead of restoring it
+        #[clap(long)]
+        delete: bool,
+    },
+    /// Scan a directory tree for insecurely-permissioned folders
+    Audit {
+        /// Directory tree to scan
+        #[clap(value_parser)]
+        path: PathBuf,
+        /// Narrow the exposed permissions/ACEs on every vulnerable folder
+        /// found, without hiding or locking the folder the way `lock` does
+        #[clap(long)]
+        fix: bool,
     },
 }
 
@@ -39,11 +58,21 @@ impl Args {
 }
 
 impl Action {
-    /// Execute the selected action
+    /// Execute the selected action using the platform's default
+    /// [`LockerConfig`].
     pub fn execute(&self) -> Result<(), LockerError> {
+        self.execute_with_config(LockerConfig::default())
+    }
+
+    /// Execute the selected action with a caller-supplied [`LockerConfig`],
+    /// letting library consumers drive locking with their own settings.
+    pub fn execute_with_config(&self, config: LockerConfig) -> Result<(), LockerError> {
         match self {
-            Action::Lock { folder } => lock_folder(folder.as_deref()),
-            Action::Unlock { folder } => unlock_folder(folder.as_deref()),
+            Action::Lock { folder, deny } => lock_folder(folder.as_deref(), config, deny.clone()),
+            Action::Unlock { folder, delete } => {
+                unlock_folder(folder.as_deref(), config, *delete)
+            }
+            Action::Audit { path, fix } => audit(path, *fix).map(|_| ()),
         }
     }
 }
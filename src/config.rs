@@ -0,0 +1,75 @@
+use std::path::PathBuf;
+
+/// How a locked folder's hidden path is derived from its visible name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HiddenNaming {
+    /// Prefix the folder name with a dot, e.g. `Locker` -> `.Locker`.
+    /// Hides the folder on Unix for free and matches this tool's
+    /// historical `.Locker` convention on Windows.
+    DotPrefix,
+}
+
+impl HiddenNaming {
+    pub fn apply(&self, name: &str) -> String {
+        match self {
+            HiddenNaming::DotPrefix => format!(".{}", name),
+        }
+    }
+}
+
+/// Tunable knobs for where and how a locker stores its bookkeeping,
+/// allowing the crate to be driven as a library with its own
+/// configuration rather than the historical hardcoded constants.
+#[derive(Debug, Clone)]
+pub struct LockerConfig {
+    /// Name of the metadata file written inside the hidden folder.
+    pub metadata_file: String,
+    /// Strategy used to derive the hidden path from the visible one.
+    pub hidden_naming: HiddenNaming,
+    /// Cost factor passed to bcrypt when hashing the lock password.
+    pub bcrypt_cost: u32,
+    /// Directory the metadata file is written to, relative to the hidden
+    /// folder. `None` means directly inside the hidden folder (the
+    /// historical behavior).
+    pub metadata_dir: Option<PathBuf>,
+}
+
+impl LockerConfig {
+    /// Creates a config overriding only the metadata filename, keeping
+    /// every other default.
+    pub fn new(metadata_file: impl Into<String>) -> Self {
+        Self {
+            metadata_file: metadata_file.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Resolves the directory a locked folder's metadata file lives in.
+    pub fn metadata_dir<'a>(&'a self, hidden_path: &'a std::path::Path) -> std::path::PathBuf {
+        match &self.metadata_dir {
+            Some(dir) => hidden_path.join(dir),
+            None => hidden_path.to_path_buf(),
+        }
+    }
+}
+
+impl Default for LockerConfig {
+    fn default() -> Self {
+        Self {
+            metadata_file: ".locker_metadata".to_string(),
+            hidden_naming: HiddenNaming::DotPrefix,
+            bcrypt_cost: bcrypt::DEFAULT_COST,
+            metadata_dir: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dot_prefix_prepends_a_single_dot() {
+        assert_eq!(HiddenNaming::DotPrefix.apply("Locker"), ".Locker");
+    }
+}
@@ -16,6 +16,19 @@ pub enum LockerError {
     #[error("Folder is not locked")]
     FolderNotLocked,
 
+    /// Another process already holds the advisory lock on this folder.
+    #[error("Folder is locked by {hostname} (pid {process_id}) since {date}")]
+    Locked {
+        hostname: String,
+        process_id: u32,
+        date: String,
+    },
+
+    /// The path is a junction or symlink; operating on it could affect a
+    /// folder outside the one the caller intended to lock/unlock.
+    #[error("Refusing to operate on reparse point: {0}")]
+    ReparsePointEncountered(PathBuf),
+
     /// Failed to perform a file or folder operation at the specified path.
     #[error("Failed to {operation} at {path}: {error}")]
     FileOperationFailed {
@@ -1,135 +1,218 @@
-use native_windows_gui as nwg;
-use std::cell::RefCell;
-use std::ffi::OsStr;
+//! Windows-only folder locking primitives (attributes, ACLs, reliable
+//! deletion). Everything here depends on Win32 APIs. The actual
+//! lock/unlock entry point, [`crate::folder_operations`], reaches these
+//! through [`crate::lock_backend::DefaultLockBackend`] (the Windows
+//! backend picked at compile time), and calls them directly for
+//! per-principal ACLs and for deleting a locked folder.
+#![cfg(windows)]
+
 use std::fs;
+use std::mem;
 use std::os::windows::ffi::OsStrExt;
 use std::os::windows::fs::MetadataExt;
-use std::path::Path;
-use std::rc::Rc;
-use winapi::shared::minwindef::DWORD;
-use winapi::um::fileapi::SetFileAttributesW;
-use winapi::um::winbase::MoveFileExW;
-use winapi::um::winbase::MOVEFILE_REPLACE_EXISTING;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use winapi::shared::minwindef::{DWORD, FILETIME};
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::fileapi::{
+    CreateFileW, SetFileAttributesW, SetFileInformationByHandle, SetFileTime, OPEN_EXISTING,
+};
+use winapi::um::handleapi::{CloseHandle, INVALID_HANDLE_VALUE};
+use winapi::um::minwinbase::FileRenameInfo;
+use winapi::um::winbase::LookupAccountNameW;
+use winapi::um::winbase::{
+    FILE_FLAG_BACKUP_SEMANTICS, FILE_FLAG_DELETE_ON_CLOSE, FILE_FLAG_OPEN_REPARSE_POINT,
+};
 use winapi::um::winnt::{
-    DELETE, FILE_ATTRIBUTE_HIDDEN, FILE_ATTRIBUTE_READONLY, FILE_ATTRIBUTE_SYSTEM,
+    SID_NAME_USE, DELETE, FILE_ATTRIBUTE_HIDDEN, FILE_ATTRIBUTE_READONLY, FILE_ATTRIBUTE_SYSTEM,
+    FILE_GENERIC_READ, FILE_GENERIC_WRITE, FILE_RENAME_INFO, FILE_SHARE_DELETE, FILE_SHARE_READ,
+    FILE_SHARE_WRITE, FILE_WRITE_ATTRIBUTES, SECURITY_MAX_SID_SIZE,
 };
 use windows_acl::acl::ACL;
-use windows_sys::Win32::Security::{CreateWellKnownSid, WinWorldSid, PSID};
+use windows_sys::Win32::Security::{
+    CreateWellKnownSid, WinAuthenticatedUserSid, WinWorldSid, PSID,
+};
+
+use crate::acl_rule::{AccessRight, AclRule, Principal, WellKnownPrincipal};
+use crate::atomic_file::{read_atomic, write_atomic};
+use crate::error::LockerError;
+
+/// Name of the saved-state file written by
+/// [`FileManager::persist_folder_state`].
+const STATE_FILE_NAME: &str = ".locker_state";
+
+/// A folder's attributes and timestamps as they were immediately before
+/// [`FileManager::set_folder_attributes`] overwrote them, so the unlock
+/// path can restore the folder exactly rather than zeroing its state.
+///
+/// Timestamps are kept as the raw `u64` Windows file-time values
+/// ([`std::os::windows::fs::MetadataExt`] already exposes them in this
+/// form), which round-trips losslessly through [`winapi::shared::minwindef::FILETIME`]'s
+/// low/high 32-bit halves.
+#[derive(Debug, Clone, Copy)]
+pub struct FolderState {
+    attributes: DWORD,
+    created: u64,
+    accessed: u64,
+    modified: u64,
+}
+
+impl FolderState {
+    /// Reads `path`'s current attributes and timestamps.
+    pub fn capture(path: &Path) -> Result<Self, LockerError> {
+        let metadata = fs::metadata(path).map_err(|e| LockerError::FileOperationFailed {
+            operation: "stat".to_string(),
+            path: path.to_path_buf(),
+            error: e.to_string(),
+        })?;
+        Ok(Self {
+            attributes: metadata.file_attributes(),
+            created: metadata.creation_time(),
+            accessed: metadata.last_access_time(),
+            modified: metadata.last_write_time(),
+        })
+    }
+
+    fn serialize(&self) -> String {
+        format!(
+            "{}:{}:{}:{}",
+            self.attributes, self.created, self.accessed, self.modified
+        )
+    }
+
+    fn deserialize(text: &str) -> Option<Self> {
+        let mut fields = text.trim().split(':');
+        Some(Self {
+            attributes: fields.next()?.parse().ok()?,
+            created: fields.next()?.parse().ok()?,
+            accessed: fields.next()?.parse().ok()?,
+            modified: fields.next()?.parse().ok()?,
+        })
+    }
+}
 
 pub struct FileManager;
 
 impl FileManager {
-    pub fn set_file_attributes(path: &Path) -> Result<(), DWORD> {
-        use std::os::windows::ffi::OsStrExt;
-        let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    pub fn set_file_attributes(path: &Path) -> Result<(), LockerError> {
+        reject_reparse_point(path)?;
+        let wide = to_wide(path);
         let result = unsafe {
             SetFileAttributesW(wide.as_ptr(), FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM)
         };
-        if result == 0 {
-            Err(unsafe { winapi::um::errhandlingapi::GetLastError() })
-        } else {
-            Ok(())
-        }
+        win32_result(result, "set attributes on", path)
     }
 
-    pub fn set_folder_attributes(name: &str) -> Result<(), DWORD> {
-        use std::os::windows::ffi::OsStrExt;
-        let wide: Vec<u16> = std::ffi::OsStr::new(name)
-            .encode_wide()
-            .chain(Some(0))
-            .collect();
+    /// Hides and locks down `name`, first saving its current attributes
+    /// and timestamps (via [`FileManager::persist_folder_state`]) so
+    /// [`FileManager::remove_folder_attributes`] can later restore them
+    /// instead of resetting the folder to an all-zero attribute state.
+    pub fn set_folder_attributes(name: &str) -> Result<(), LockerError> {
+        let path = Path::new(name);
+        reject_reparse_point(path)?;
+
+        let state = FolderState::capture(path)?;
+        Self::persist_folder_state(path, state)?;
+
+        let wide = to_wide(path);
         let result = unsafe {
             SetFileAttributesW(
                 wide.as_ptr(),
                 FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM | FILE_ATTRIBUTE_READONLY,
             )
         };
-        if result == 0 {
-            Err(unsafe { winapi::um::errhandlingapi::GetLastError() })
-        } else {
-            Ok(())
-        }
+        win32_result(result, "set attributes on", path)
     }
 
-    pub fn remove_folder_attributes(name: &str) -> Result<(), DWORD> {
-        use std::os::windows::ffi::OsStrExt;
-        let wide: Vec<u16> = std::ffi::OsStr::new(name)
-            .encode_wide()
-            .chain(Some(0))
-            .collect();
-        let result = unsafe { SetFileAttributesW(wide.as_ptr(), 0) };
-        if result == 0 {
-            Err(unsafe { winapi::um::errhandlingapi::GetLastError() })
-        } else {
-            Ok(())
+    /// Reverses [`FileManager::set_folder_attributes`]. If a saved state
+    /// from that call is still on disk, restores `name`'s original
+    /// attributes and re-applies its original creation/access/write
+    /// timestamps via `SetFileTime` rather than zeroing everything; falls
+    /// back to clearing attributes outright if no saved state exists
+    /// (e.g. this folder was never locked through `set_folder_attributes`).
+    pub fn remove_folder_attributes(name: &str) -> Result<(), LockerError> {
+        let path = Path::new(name);
+        reject_reparse_point(path)?;
+
+        if let Ok(state) = Self::load_folder_state(path) {
+            Self::restore_folder_state(path, state)?;
+            let _ = fs::remove_file(path.join(STATE_FILE_NAME));
+            return Ok(());
         }
+
+        let wide = to_wide(path);
+        let result = unsafe { SetFileAttributesW(wide.as_ptr(), 0) };
+        win32_result(result, "remove attributes from", path)
     }
 
-    pub fn prevent_folder_deletion(folder_path: &str) -> Result<(), DWORD> {
-        Self::modify_folder_deletion_permissions(folder_path, true)
+    /// Writes `state` into `dir`'s saved-state file, using the same
+    /// shared crash-safe write-temp-then-rename primitive as
+    /// [`crate::metadata`].
+    pub fn persist_folder_state(dir: &Path, state: FolderState) -> Result<(), LockerError> {
+        write_atomic(dir, STATE_FILE_NAME, &state.serialize())
     }
 
-    pub fn allow_folder_deletion(folder_path: &str) -> Result<(), DWORD> {
-        println!("Attempting to allow folder deletion for: {}", folder_path);
+    /// Reads back the saved state written by
+    /// [`FileManager::persist_folder_state`].
+    pub fn load_folder_state(dir: &Path) -> Result<FolderState, LockerError> {
+        let path = dir.join(STATE_FILE_NAME);
+        let contents = read_atomic(dir, STATE_FILE_NAME)?;
+        FolderState::deserialize(&contents).ok_or_else(|| LockerError::FileOperationFailed {
+            operation: "read state file at".to_string(),
+            path,
+            error: "state file is empty or malformed".to_string(),
+        })
+    }
 
-        if !Self::verify_password() {
-            println!("Password verification failed. Deletion not allowed.");
-            return Ok(());
-        }
+    /// Restores `path`'s attributes and timestamps from a previously
+    /// captured [`FolderState`].
+    fn restore_folder_state(path: &Path, state: FolderState) -> Result<(), LockerError> {
+        let wide = to_wide(path);
+        let result = unsafe { SetFileAttributesW(wide.as_ptr(), state.attributes) };
+        win32_result(result, "restore attributes on", path)?;
 
-        let wide_path: Vec<u16> = OsStr::new(folder_path)
-            .encode_wide()
-            .chain(Some(0))
-            .collect();
-
-        if let Ok(metadata) = fs::metadata(folder_path) {
-            let attributes = metadata.file_attributes();
-            if attributes & FILE_ATTRIBUTE_READONLY != 0 {
-                let new_attributes = attributes & !FILE_ATTRIBUTE_READONLY;
-                let result = unsafe { SetFileAttributesW(wide_path.as_ptr(), new_attributes) };
-                if result == 0 {
-                    return Err(unsafe { winapi::um::errhandlingapi::GetLastError() });
-                }
+        unsafe {
+            let handle = CreateFileW(
+                wide.as_ptr(),
+                FILE_WRITE_ATTRIBUTES,
+                FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+                ptr::null_mut(),
+                OPEN_EXISTING,
+                FILE_FLAG_BACKUP_SEMANTICS,
+                ptr::null_mut(),
+            );
+            if handle == INVALID_HANDLE_VALUE {
+                return Err(LockerError::FileOperationFailed {
+                    operation: "open for timestamp restore".to_string(),
+                    path: path.to_path_buf(),
+                    error: format!("GetLastError={}", GetLastError()),
+                });
             }
-        }
-
-        if let Err(e) = Self::modify_folder_deletion_permissions(folder_path, false) {
-            println!("Failed to modify folder deletion permissions: {:?}", e);
-            return Err(e);
-        }
-
-        if let Err(e) = Self::remove_folder_attributes(folder_path) {
-            println!("Failed to remove folder attributes: {:?}", e);
-            return Err(e);
-        }
 
-        if let Err(e) = Self::grant_delete_permission_to_everyone(folder_path) {
-            println!("Failed to grant delete permission to everyone: {:?}", e);
-            return Err(e);
-        }
+            let created = filetime_from_u64(state.created);
+            let accessed = filetime_from_u64(state.accessed);
+            let modified = filetime_from_u64(state.modified);
+            let result = SetFileTime(handle, &created, &accessed, &modified);
+            let last_error = GetLastError();
+            CloseHandle(handle);
 
-        // Try to rename the folder to itself to release any locks
-        let wide_path: Vec<u16> = folder_path.encode_utf16().chain(Some(0)).collect();
-        let result = unsafe {
-            MoveFileExW(
-                wide_path.as_ptr(),
-                wide_path.as_ptr(),
-                MOVEFILE_REPLACE_EXISTING,
-            )
-        };
-        if result == 0 {
-            println!("Failed to release locks on the folder");
-        } else {
-            println!("Successfully released locks on the folder");
+            if result == 0 {
+                return Err(LockerError::FileOperationFailed {
+                    operation: "restore timestamps on".to_string(),
+                    path: path.to_path_buf(),
+                    error: format!("GetLastError={}", last_error),
+                });
+            }
         }
 
-        println!("Successfully allowed folder deletion for: {}", folder_path);
         Ok(())
     }
 
-    fn modify_folder_deletion_permissions(folder_path: &str, deny: bool) -> Result<(), DWORD> {
-        // Get the current ACL
-        let mut acl = ACL::from_file_path(folder_path, false)?;
+    pub fn prevent_folder_deletion(folder_path: &str) -> Result<(), LockerError> {
+        reject_reparse_point(Path::new(folder_path))?;
+
+        let mut acl = ACL::from_file_path(folder_path, false).map_err(|e| acl_error(folder_path, e))?;
 
         let mut everyone_sid = [0u8; 16];
         let mut sid_size = everyone_sid.len() as u32;
@@ -142,102 +225,436 @@ impl FileManager {
             );
         }
 
-        if deny {
-            // Deny delete permissions for everyone
-            acl.deny(everyone_sid.as_ptr() as *mut _, false, DELETE)?;
-            println!("Delete permissions denied for folder: {}", folder_path);
-        } else {
-            // Remove the deny rule for delete permissions for everyone
-            acl.remove(everyone_sid.as_ptr() as *mut _, None, Some(DELETE != 0))?;
-            println!("Delete permissions allowed for folder: {}", folder_path);
+        acl.deny(everyone_sid.as_ptr() as *mut _, false, DELETE)
+            .map_err(|e| acl_error(folder_path, e))?;
+        println!("Delete permissions denied for folder: {}", folder_path);
+
+        Ok(())
+    }
+
+    /// Builds a folder's DACL from an arbitrary set of per-principal
+    /// rules, instead of the single hardcoded "deny delete to Everyone"
+    /// policy. Lets callers express things like "deny Authenticated
+    /// Users but keep access for the owner".
+    pub fn set_deletion_policy(path: &str, rules: &[AclRule]) -> Result<(), LockerError> {
+        reject_reparse_point(Path::new(path))?;
+
+        let mut acl = ACL::from_file_path(path, false).map_err(|e| acl_error(path, e))?;
+
+        for rule in rules {
+            let sid = resolve_sid(&rule.principal, path)?;
+            let mask = access_mask(&rule.access);
+            let result = if rule.allow {
+                acl.allow(sid.as_ptr() as *mut _, false, mask)
+            } else {
+                acl.deny(sid.as_ptr() as *mut _, false, mask)
+            };
+            result.map_err(|e| acl_error(path, e))?;
         }
 
         Ok(())
     }
 
-    fn grant_delete_permission_to_everyone(folder_path: &str) -> Result<(), DWORD> {
-        println!(
-            "Granting delete permission to everyone for: {}",
-            folder_path
-        );
+    /// Reliably tears down a previously-locked directory tree.
+    ///
+    /// `remove_dir_all`-style recursion is racy here: Windows schedules
+    /// deletions asynchronously, so a parent can fail to go away because
+    /// a child's deletion hasn't committed yet. Instead, for every entry
+    /// (children first), this strips `FILE_ATTRIBUTE_READONLY`, opens a
+    /// handle with `DELETE | FILE_FLAG_BACKUP_SEMANTICS`, renames it to a
+    /// unique name in its own parent directory via
+    /// `SetFileInformationByHandle`/`FILE_RENAME_INFO`, and marks it
+    /// `FILE_FLAG_DELETE_ON_CLOSE` so the delete commits when the handle
+    /// closes. All paths are issued through the `\\?\` verbatim prefix so
+    /// deeply nested locked trees past `MAX_PATH` still unwind.
+    pub fn remove_locked_folder(path: &Path) -> Result<(), LockerError> {
+        Self::remove_locked_entry(path)
+    }
 
-        let mut acl = ACL::from_file_path(folder_path, false)?;
+    fn remove_locked_entry(path: &Path) -> Result<(), LockerError> {
+        let metadata = fs::symlink_metadata(path).map_err(|e| LockerError::FileOperationFailed {
+            operation: "stat".to_string(),
+            path: path.to_path_buf(),
+            error: e.to_string(),
+        })?;
+
+        // A directory entry that's a junction/symlink must not be recursed
+        // into: walking through one would rename and delete-on-close files
+        // under its real target instead of the tree the caller asked to
+        // remove. `symlink_metadata` reports such links as non-directories,
+        // so they skip this branch and are removed as the link itself
+        // below; any other reparse-point directory is rejected here.
+        if metadata.is_dir() {
+            reject_reparse_point(path)?;
+
+            let entries = fs::read_dir(path).map_err(|e| LockerError::FileOperationFailed {
+                operation: "read".to_string(),
+                path: path.to_path_buf(),
+                error: e.to_string(),
+            })?;
+            for entry in entries {
+                let entry = entry.map_err(|e| LockerError::FileOperationFailed {
+                    operation: "read entry in".to_string(),
+                    path: path.to_path_buf(),
+                    error: e.to_string(),
+                })?;
+                Self::remove_locked_entry(&entry.path())?;
+            }
+        }
+
+        if metadata.file_attributes() & FILE_ATTRIBUTE_READONLY != 0 {
+            let _ = Self::remove_folder_attributes(path.to_str().unwrap());
+        }
+
+        Self::rename_and_delete_on_close(path)
+    }
+
+    fn rename_and_delete_on_close(path: &Path) -> Result<(), LockerError> {
+        let verbatim_path = to_verbatim_wide(path);
 
-        let mut everyone_sid = [0u8; 16];
-        let mut sid_size = everyone_sid.len() as u32;
         unsafe {
-            CreateWellKnownSid(
-                WinWorldSid,
-                std::ptr::null_mut(),
-                everyone_sid.as_mut_ptr() as PSID,
-                &mut sid_size,
+            let handle = CreateFileW(
+                verbatim_path.as_ptr(),
+                DELETE,
+                FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+                ptr::null_mut(),
+                OPEN_EXISTING,
+                FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_DELETE_ON_CLOSE | FILE_FLAG_OPEN_REPARSE_POINT,
+                ptr::null_mut(),
+            );
+            if handle == INVALID_HANDLE_VALUE {
+                return Err(LockerError::FileOperationFailed {
+                    operation: "open for deletion".to_string(),
+                    path: path.to_path_buf(),
+                    error: format!("GetLastError={}", GetLastError()),
+                });
+            }
+
+            let temp_name: Vec<u16> = unique_temp_name().encode_utf16().collect();
+            let header_size = mem::size_of::<FILE_RENAME_INFO>();
+            let mut buffer = vec![0u8; header_size + temp_name.len() * mem::size_of::<u16>()];
+            let rename_info = buffer.as_mut_ptr() as *mut FILE_RENAME_INFO;
+            (*rename_info).ReplaceIfExists = 1;
+            (*rename_info).RootDirectory = ptr::null_mut();
+            (*rename_info).FileNameLength = (temp_name.len() * mem::size_of::<u16>()) as u32;
+            ptr::copy_nonoverlapping(
+                temp_name.as_ptr(),
+                (*rename_info).FileName.as_mut_ptr(),
+                temp_name.len(),
             );
+
+            let result = SetFileInformationByHandle(
+                handle,
+                FileRenameInfo,
+                buffer.as_mut_ptr() as *mut _,
+                buffer.len() as u32,
+            );
+            let last_error = GetLastError();
+            CloseHandle(handle);
+
+            if result == 0 {
+                return Err(LockerError::FileOperationFailed {
+                    operation: "rename before delete-on-close".to_string(),
+                    path: path.to_path_buf(),
+                    error: format!("GetLastError={}", last_error),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Prefixes `path` with the `\\?\` verbatim marker (unless already
+/// present) and encodes it as a NUL-terminated wide string, so Win32
+/// calls bypass `MAX_PATH` and path-normalization entirely.
+///
+/// Uses [`absolutize`] rather than `Path::canonicalize` to make the path
+/// absolute: `canonicalize` resolves symlinks/junctions along the way,
+/// which would silently redirect the delete-on-close handle opened below
+/// to a reparse point's target instead of the entry itself.
+fn to_verbatim_wide(path: &Path) -> Vec<u16> {
+    let absolute = absolutize(path);
+    let as_string = absolute.to_string_lossy();
+    let verbatim = if as_string.starts_with(r"\\?\") {
+        absolute
+    } else {
+        PathBuf::from(format!(r"\\?\{}", as_string))
+    };
+    verbatim.as_os_str().encode_wide().chain(Some(0)).collect()
+}
+
+/// Makes `path` absolute by joining it onto the current directory when
+/// it's relative, without resolving any symlinks/junctions in it.
+fn absolutize(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map(|cwd| cwd.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    }
+}
+
+fn access_mask(rights: &[AccessRight]) -> DWORD {
+    rights.iter().fold(0, |mask, right| {
+        mask | match right {
+            AccessRight::Delete => DELETE,
+            AccessRight::Write => FILE_GENERIC_WRITE,
+            AccessRight::Read => FILE_GENERIC_READ,
+        }
+    })
+}
+
+fn resolve_sid(principal: &Principal, context_path: &str) -> Result<Vec<u8>, LockerError> {
+    match principal {
+        Principal::WellKnown(well_known) => {
+            let sid_type = match well_known {
+                WellKnownPrincipal::Everyone => WinWorldSid,
+                WellKnownPrincipal::AuthenticatedUsers => WinAuthenticatedUserSid,
+            };
+            let mut sid = vec![0u8; SECURITY_MAX_SID_SIZE as usize];
+            let mut sid_size = sid.len() as u32;
+            let succeeded = unsafe {
+                CreateWellKnownSid(
+                    sid_type,
+                    ptr::null_mut(),
+                    sid.as_mut_ptr() as PSID,
+                    &mut sid_size,
+                )
+            };
+            if succeeded == 0 {
+                return Err(LockerError::FileOperationFailed {
+                    operation: "resolve well-known SID for".to_string(),
+                    path: PathBuf::from(context_path),
+                    error: format!("GetLastError={}", unsafe { GetLastError() }),
+                });
+            }
+            sid.truncate(sid_size as usize);
+            Ok(sid)
         }
+        Principal::Account(account) => resolve_account_sid(account, context_path),
+    }
+}
+
+fn resolve_account_sid(account: &str, context_path: &str) -> Result<Vec<u8>, LockerError> {
+    let wide_account: Vec<u16> = std::ffi::OsStr::new(account)
+        .encode_wide()
+        .chain(Some(0))
+        .collect();
+    let mut sid_size: u32 = 0;
+    let mut domain_size: u32 = 0;
+    let mut sid_use: SID_NAME_USE = 0;
 
-        acl.allow(everyone_sid.as_ptr() as *mut _, false, DELETE)?;
-        println!(
-            "Delete permission granted to everyone for folder: {}",
-            folder_path
+    unsafe {
+        LookupAccountNameW(
+            ptr::null(),
+            wide_account.as_ptr(),
+            ptr::null_mut(),
+            &mut sid_size,
+            ptr::null_mut(),
+            &mut domain_size,
+            &mut sid_use,
         );
+    }
+
+    if sid_size == 0 {
+        return Err(LockerError::FileOperationFailed {
+            operation: "resolve account for".to_string(),
+            path: PathBuf::from(context_path),
+            error: format!("account {} not found (GetLastError={})", account, unsafe {
+                GetLastError()
+            }),
+        });
+    }
+
+    let mut sid = vec![0u8; sid_size as usize];
+    let mut domain = vec![0u16; domain_size as usize];
 
+    let succeeded = unsafe {
+        LookupAccountNameW(
+            ptr::null(),
+            wide_account.as_ptr(),
+            sid.as_mut_ptr() as PSID,
+            &mut sid_size,
+            domain.as_mut_ptr(),
+            &mut domain_size,
+            &mut sid_use,
+        )
+    };
+
+    if succeeded == 0 {
+        return Err(LockerError::FileOperationFailed {
+            operation: "resolve account for".to_string(),
+            path: PathBuf::from(context_path),
+            error: format!("account {} (GetLastError={})", account, unsafe {
+                GetLastError()
+            }),
+        });
+    }
+
+    Ok(sid)
+}
+
+fn win32_result(result: DWORD, operation: &str, path: &Path) -> Result<(), LockerError> {
+    if result == 0 {
+        Err(LockerError::FileOperationFailed {
+            operation: operation.to_string(),
+            path: path.to_path_buf(),
+            error: format!("GetLastError={}", unsafe {
+                winapi::um::errhandlingapi::GetLastError()
+            }),
+        })
+    } else {
         Ok(())
     }
+}
+
+fn acl_error(path: &str, code: DWORD) -> LockerError {
+    LockerError::FileOperationFailed {
+        operation: "modify ACL for".to_string(),
+        path: PathBuf::from(path),
+        error: format!("Win32 error {}", code),
+    }
+}
+
+/// Opens `path` with `FILE_FLAG_OPEN_REPARSE_POINT` and queries
+/// `FSCTL_GET_REPARSE_POINT`, refusing to continue if it names a
+/// junction or symlink. Attribute/ACL mutation must treat reparse points
+/// as a hard boundary: following one could hide, mark-system, or deny
+/// delete on a folder entirely outside the one the caller intended to
+/// lock or unlock.
+pub(crate) fn reject_reparse_point(path: &Path) -> Result<(), LockerError> {
+    use winapi::um::ioapiset::DeviceIoControl;
+    use winapi::um::winbase::FILE_FLAG_OPEN_REPARSE_POINT;
+    use winapi::um::winioctl::FSCTL_GET_REPARSE_POINT;
+    use winapi::um::winnt::MAXIMUM_REPARSE_DATA_BUFFER_SIZE;
 
-    fn verify_password() -> bool {
-        nwg::init().expect("Failed to init Native Windows GUI");
-        let mut window = Default::default();
-        let mut password = Default::default();
-        let mut submit = Default::default();
-
-        nwg::Window::builder()
-            .size((300, 115))
-            .position((300, 300))
-            .title("Enter Password")
-            .build(&mut window)
-            .expect("Failed to build window");
-
-        nwg::TextInput::builder()
-            .text("")
-            .position((10, 10))
-            .size((280, 25))
-            .password(Some('*'))
-            .parent(&window)
-            .build(&mut password)
-            .expect("Failed to build text input");
-
-        nwg::Button::builder()
-            .text("Submit")
-            .position((100, 45))
-            .size((100, 25))
-            .parent(&window)
-            .build(&mut submit)
-            .expect("Failed to build button");
-
-        let window_handle = window.handle;
-
-        let result = Rc::new(RefCell::new(false));
-        let result_clone = result.clone();
-        let handler =
-            nwg::full_bind_event_handler(&window.handle, move |evt, _evt_data, handle| match evt {
-                nwg::Event::OnButtonClick => {
-                    if handle == submit {
-                        if password.text() == "your_password" {
-                            *result_clone.borrow_mut() = true;
-                        }
-                        nwg::stop_thread_dispatch();
-                    }
-                }
-                nwg::Event::OnWindowClose => {
-                    if handle == window_handle {
-                        nwg::stop_thread_dispatch();
-                    }
-                }
-                _ => {}
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let wide = to_wide(path);
+    unsafe {
+        let handle = CreateFileW(
+            wide.as_ptr(),
+            0,
+            FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+            ptr::null_mut(),
+            OPEN_EXISTING,
+            FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_OPEN_REPARSE_POINT,
+            ptr::null_mut(),
+        );
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(LockerError::FileOperationFailed {
+                operation: "open".to_string(),
+                path: path.to_path_buf(),
+                error: format!("GetLastError={}", GetLastError()),
             });
+        }
+
+        let mut buffer = vec![0u8; MAXIMUM_REPARSE_DATA_BUFFER_SIZE as usize];
+        let mut bytes_returned: DWORD = 0;
+        let succeeded = DeviceIoControl(
+            handle,
+            FSCTL_GET_REPARSE_POINT,
+            ptr::null_mut(),
+            0,
+            buffer.as_mut_ptr() as *mut _,
+            buffer.len() as u32,
+            &mut bytes_returned,
+            ptr::null_mut(),
+        );
+        CloseHandle(handle);
+
+        if succeeded != 0 {
+            return Err(LockerError::ReparsePointEncountered(path.to_path_buf()));
+        }
+    }
+
+    Ok(())
+}
+
+fn to_wide(path: &Path) -> Vec<u16> {
+    path.as_os_str().encode_wide().chain(Some(0)).collect()
+}
+
+/// Splits a Windows file-time `u64` into its `FILETIME` low/high halves.
+fn filetime_from_u64(value: u64) -> FILETIME {
+    FILETIME {
+        dwLowDateTime: (value & 0xFFFF_FFFF) as u32,
+        dwHighDateTime: (value >> 32) as u32,
+    }
+}
+
+static TEMP_NAME_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A name unique within a single process run, suitable for the
+/// rename-then-delete-on-close dance (the name itself is never observed
+/// since the handle is deleted on close).
+fn unique_temp_name() -> String {
+    let counter = TEMP_NAME_COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!(".locker-delete-{}-{}", std::process::id(), counter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn access_mask_combines_requested_rights() {
+        assert_eq!(access_mask(&[]), 0);
+        assert_eq!(access_mask(&[AccessRight::Delete]), DELETE);
+        assert_eq!(
+            access_mask(&[AccessRight::Delete, AccessRight::Write]),
+            DELETE | FILE_GENERIC_WRITE
+        );
+        assert_eq!(
+            access_mask(&[AccessRight::Read, AccessRight::Write, AccessRight::Delete]),
+            FILE_GENERIC_READ | FILE_GENERIC_WRITE | DELETE
+        );
+    }
 
-        nwg::dispatch_thread_events();
-        nwg::unbind_event_handler(&handler);
+    #[test]
+    fn resolve_sid_rejects_unknown_account() {
+        let err = resolve_sid(
+            &Principal::Account("definitely-not-a-real-account".to_string()),
+            "C:\\Locker",
+        )
+        .unwrap_err();
+        assert!(matches!(err, LockerError::FileOperationFailed { .. }));
+    }
+
+    #[test]
+    fn resolve_sid_resolves_well_known_principals() {
+        assert!(resolve_sid(&Principal::WellKnown(WellKnownPrincipal::Everyone), "C:\\Locker").is_ok());
+        assert!(resolve_sid(
+            &Principal::WellKnown(WellKnownPrincipal::AuthenticatedUsers),
+            "C:\\Locker"
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn folder_state_round_trips_through_serialize_deserialize() {
+        let state = FolderState {
+            attributes: FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM,
+            created: 132_000_000_000_000_000,
+            accessed: 132_000_000_000_000_001,
+            modified: 132_000_000_000_000_002,
+        };
+
+        let restored = FolderState::deserialize(&state.serialize()).unwrap();
+
+        assert_eq!(restored.attributes, state.attributes);
+        assert_eq!(restored.created, state.created);
+        assert_eq!(restored.accessed, state.accessed);
+        assert_eq!(restored.modified, state.modified);
+    }
 
-        Rc::try_unwrap(result).unwrap().into_inner()
+    #[test]
+    fn folder_state_deserialize_rejects_malformed_text() {
+        assert!(FolderState::deserialize("not:enough:fields").is_none());
+        assert!(FolderState::deserialize("not,a,number,here").is_none());
     }
 }
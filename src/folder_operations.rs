@@ -1,15 +1,23 @@
 use colored::*;
 use dialoguer::{theme::ColorfulTheme, Password};
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::ProgressBar;
 use log::{error, info, warn};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::thread;
 use std::time::Duration;
 
+#[cfg(windows)]
+use crate::acl_rule::{AccessRight, AclRule, Principal};
+use crate::config::LockerConfig;
 use crate::error::LockerError;
+#[cfg(windows)]
+use crate::file_manager::FileManager;
+use crate::lock_backend::{DefaultLockBackend, LockBackend};
+use crate::lock_guard::{LockGuard, LockLevel};
 use crate::metadata::{read_metadata, remove_metadata, write_metadata};
 use crate::password::{get_password, hash_password, verify_password};
+#[cfg(unix)]
 use crate::permission_manager::PermissionManager;
 
 // Add derive macros for better debugging and cloning capabilities
@@ -17,24 +25,37 @@ use crate::permission_manager::PermissionManager;
 struct FolderOperator {
     folder_path: PathBuf,
     hidden_path: PathBuf,
+    config: LockerConfig,
+    /// Principals (account names) to deny delete access to instead of the
+    /// default "Everyone" policy. Only honored on Windows.
+    deny_principals: Vec<String>,
 }
 
 impl FolderOperator {
-    fn new(folder: Option<&Path>) -> Result<Self, LockerError> {
-        let (folder_path, hidden_path) = Self::get_folder_paths(folder)?;
+    fn new(
+        folder: Option<&Path>,
+        config: LockerConfig,
+        deny_principals: Vec<String>,
+    ) -> Result<Self, LockerError> {
+        let (folder_path, hidden_path) = Self::get_folder_paths(folder, &config)?;
         Ok(Self {
             folder_path,
             hidden_path,
+            config,
+            deny_principals,
         })
     }
 
-    fn get_folder_paths(folder: Option<&Path>) -> Result<(PathBuf, PathBuf), LockerError> {
+    fn get_folder_paths(
+        folder: Option<&Path>,
+        config: &LockerConfig,
+    ) -> Result<(PathBuf, PathBuf), LockerError> {
         let folder_path = folder.unwrap_or_else(|| Path::new("."));
         let folder_name = folder_path
             .file_name()
             .and_then(|name| name.to_str())
             .ok_or(LockerError::InvalidFolderName)?;
-        let hidden_path = folder_path.with_file_name(format!(".{}", folder_name));
+        let hidden_path = folder_path.with_file_name(config.hidden_naming.apply(folder_name));
         Ok((folder_path.to_path_buf(), hidden_path))
     }
 
@@ -61,15 +82,27 @@ impl FolderOperator {
             return Err(LockerError::FolderNotLocked);
         }
 
+        if !is_locking {
+            match DefaultLockBackend::is_locked(&self.hidden_path) {
+                Ok(false) => warn!(
+                    "Hidden folder {:?} exists but its lock attributes look cleared; proceeding anyway",
+                    self.hidden_path
+                ),
+                Ok(true) => {}
+                Err(e) => warn!("Could not verify lock state of {:?}: {}", self.hidden_path, e),
+            }
+        }
+
         spinner.finish_with_message("Folder status check complete.");
         Ok(())
     }
 
     fn lock(&self) -> Result<(), LockerError> {
         self.check_folder_status(true)?;
+        let _guard = LockGuard::acquire(&self.folder_path, LockLevel::Exclusive)?;
 
         let password = get_password()?;
-        let hashed_password = hash_password(&password)?;
+        let hashed_password = hash_password(&password, self.config.bcrypt_cost)?;
 
         let pb = self.create_progress_bar(4);
 
@@ -82,12 +115,20 @@ impl FolderOperator {
         Ok(())
     }
 
-    fn unlock(&self) -> Result<(), LockerError> {
+    fn unlock(&self, delete: bool) -> Result<(), LockerError> {
         self.check_folder_status(false)?;
+        let _guard = LockGuard::acquire(&self.folder_path, LockLevel::Exclusive)?;
         self.verify_password()?;
 
         println!("{}", "Password verified successfully!".green());
 
+        if delete {
+            self.perform_delete_steps()?;
+            info!("Locked folder permanently deleted: {:?}", self.hidden_path);
+            println!("{}", "Folder permanently deleted.".green().bold());
+            return Ok(());
+        }
+
         let pb = self.create_progress_bar(3);
 
         self.perform_unlock_steps(&pb)?;
@@ -102,26 +143,11 @@ impl FolderOperator {
     // Helper methods to improve readability and maintainability
 
     fn create_spinner(&self, message: &str) -> ProgressBar {
-        let pb = ProgressBar::new_spinner();
-        pb.set_style(
-            ProgressStyle::default_spinner()
-                .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ ")
-                .template("{spinner:.green} {msg}")
-                .unwrap()
-        );
-        pb.set_message(message.to_string());
-        pb
+        crate::progress::spinner(message)
     }
 
     fn create_progress_bar(&self, steps: u64) -> ProgressBar {
-        let pb = ProgressBar::new(steps);
-        pb.set_style(
-            ProgressStyle::default_bar()
-                .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")
-                .unwrap()
-                .progress_chars("#>-"),
-        );
-        pb
+        crate::progress::bar(steps)
     }
 
     fn perform_lock_steps(
@@ -147,14 +173,43 @@ impl FolderOperator {
         }
         pb.inc(1);
 
+        #[cfg(unix)]
+        let original_mode = Some(PermissionManager::capture_mode(&self.hidden_path)?);
+        #[cfg(not(unix))]
+        let original_mode = None;
+
         let spinner = self.create_spinner("Writing metadata...");
-        write_metadata(&self.hidden_path, hashed_password)?;
+        let original_name = self
+            .folder_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or(LockerError::InvalidFolderName)?;
+        write_metadata(
+            &self.hidden_path,
+            hashed_password,
+            original_name,
+            original_mode,
+            &self.config,
+        )?;
         thread::sleep(Duration::from_secs(1));
         spinner.finish_and_clear();
         pb.inc(1);
 
         let spinner = self.create_spinner("Setting folder attributes...");
-        PermissionManager::set_attributes(self.hidden_path.to_str().unwrap())?;
+        #[cfg(windows)]
+        {
+            if self.deny_principals.is_empty() {
+                DefaultLockBackend::lock(&self.hidden_path)?;
+            } else {
+                // Hide the folder without the default Everyone deny, so the
+                // per-principal rules applied below are its delete policy.
+                let path_str = self.hidden_path.to_str().ok_or(LockerError::InvalidFolderName)?;
+                FileManager::set_folder_attributes(path_str)?;
+            }
+        }
+        #[cfg(not(windows))]
+        DefaultLockBackend::lock(&self.hidden_path)?;
+        self.apply_deny_principals()?;
         thread::sleep(Duration::from_secs(1));
         spinner.finish_and_clear();
         pb.inc(1);
@@ -162,6 +217,38 @@ impl FolderOperator {
         Ok(())
     }
 
+    /// Replaces the default "deny delete to Everyone" policy `lock` applies
+    /// with a per-principal deny rule for each `--deny <account>` the
+    /// caller passed, so users can target e.g. a specific group instead of
+    /// every principal on the machine.
+    #[cfg(windows)]
+    fn apply_deny_principals(&self) -> Result<(), LockerError> {
+        if self.deny_principals.is_empty() {
+            return Ok(());
+        }
+
+        let rules: Vec<AclRule> = self
+            .deny_principals
+            .iter()
+            .map(|principal| {
+                AclRule::deny(Principal::Account(principal.clone()), vec![AccessRight::Delete])
+            })
+            .collect();
+
+        FileManager::set_deletion_policy(self.hidden_path.to_str().unwrap(), &rules)
+    }
+
+    #[cfg(not(windows))]
+    fn apply_deny_principals(&self) -> Result<(), LockerError> {
+        if !self.deny_principals.is_empty() {
+            warn!(
+                "--deny is only supported on Windows; ignoring {} principal(s)",
+                self.deny_principals.len()
+            );
+        }
+        Ok(())
+    }
+
     fn verify_password(&self) -> Result<(), LockerError> {
         let input = Password::with_theme(&ColorfulTheme::default())
             .with_prompt("Enter password")
@@ -171,9 +258,9 @@ impl FolderOperator {
                 reason: "User interaction error".to_string(),
             })?;
 
-        let stored_password = read_metadata(&self.hidden_path)?;
+        let metadata = read_metadata(&self.hidden_path, &self.config)?;
 
-        if !verify_password(&input, &stored_password)? {
+        if !verify_password(&input, &metadata.hash)? {
             error!(
                 "Invalid password attempt for folder: {:?}",
                 self.hidden_path
@@ -186,22 +273,28 @@ impl FolderOperator {
     }
 
     fn perform_unlock_steps(&self, pb: &ProgressBar) -> Result<(), LockerError> {
+        let metadata = read_metadata(&self.hidden_path, &self.config)?;
+
         let spinner = self.create_spinner("Removing folder attributes...");
-        PermissionManager::remove_attributes(self.hidden_path.to_str().unwrap())?;
+        #[cfg(unix)]
+        match metadata.original_mode {
+            Some(mode) => PermissionManager::restore_mode(&self.hidden_path, mode)?,
+            None => DefaultLockBackend::unlock(&self.hidden_path)?,
+        }
+        #[cfg(not(unix))]
+        DefaultLockBackend::unlock(&self.hidden_path)?;
         thread::sleep(Duration::from_secs(1));
         spinner.finish_and_clear();
         pb.inc(1);
 
         let spinner = self.create_spinner("Removing metadata...");
-        remove_metadata(&self.hidden_path)?;
+        remove_metadata(&self.hidden_path, &self.config)?;
         thread::sleep(Duration::from_secs(1));
         spinner.finish_and_clear();
         pb.inc(1);
 
         let spinner = self.create_spinner("Renaming folder...");
-        let unlocked_path = self
-            .folder_path
-            .with_file_name(self.folder_path.file_name().unwrap().to_str().unwrap());
+        let unlocked_path = self.folder_path.with_file_name(metadata.original_name);
         self.rename_folder(&self.hidden_path, &unlocked_path)?;
         thread::sleep(Duration::from_secs(1));
         spinner.finish_and_clear();
@@ -210,6 +303,30 @@ impl FolderOperator {
         Ok(())
     }
 
+    /// Restores access to the hidden folder and then tears the whole tree
+    /// down, instead of renaming it back to visible. On Windows this goes
+    /// through [`FileManager::remove_locked_folder`]'s depth-first
+    /// rename+delete-on-close teardown, which is immune to the scheduled-
+    /// deletion race a plain recursive delete would hit.
+    fn perform_delete_steps(&self) -> Result<(), LockerError> {
+        let spinner = self.create_spinner("Restoring access before deletion...");
+        DefaultLockBackend::unlock(&self.hidden_path)?;
+        spinner.finish_and_clear();
+
+        let spinner = self.create_spinner("Deleting locked folder...");
+        #[cfg(windows)]
+        FileManager::remove_locked_folder(&self.hidden_path)?;
+        #[cfg(not(windows))]
+        fs::remove_dir_all(&self.hidden_path).map_err(|e| LockerError::FileOperationFailed {
+            operation: "remove".to_string(),
+            path: self.hidden_path.clone(),
+            error: e.to_string(),
+        })?;
+        spinner.finish_and_clear();
+
+        Ok(())
+    }
+
     fn create_folder(&self, path: &Path) -> Result<(), LockerError> {
         fs::create_dir(path).map_err(|e| LockerError::FileOperationFailed {
             operation: "create".to_string(),
@@ -231,10 +348,18 @@ impl FolderOperator {
     }
 }
 
-pub fn lock_folder(folder: Option<&Path>) -> Result<(), LockerError> {
-    FolderOperator::new(folder)?.lock()
+pub fn lock_folder(
+    folder: Option<&Path>,
+    config: LockerConfig,
+    deny_principals: Vec<String>,
+) -> Result<(), LockerError> {
+    FolderOperator::new(folder, config, deny_principals)?.lock()
 }
 
-pub fn unlock_folder(folder: Option<&Path>) -> Result<(), LockerError> {
-    FolderOperator::new(folder)?.unlock()
+pub fn unlock_folder(
+    folder: Option<&Path>,
+    config: LockerConfig,
+    delete: bool,
+) -> Result<(), LockerError> {
+    FolderOperator::new(folder, config, Vec::new())?.unlock(delete)
 }
@@ -0,0 +1,146 @@
+use std::path::Path;
+
+use crate::error::LockerError;
+
+/// A probe for what kind of access a path currently permits, mirroring
+/// the `access()`/`EXISTS`/`READ`/`WRITE`/`EXECUTE` bitflag convention so
+/// callers can ask "can I write here" portably before attempting a lock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessMode(u8);
+
+impl AccessMode {
+    pub const EXISTS: Self = Self(0b0001);
+    pub const READ: Self = Self(0b0010);
+    pub const WRITE: Self = Self(0b0100);
+    pub const EXECUTE: Self = Self(0b1000);
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for AccessMode {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// Platform-abstracted folder locking: hide/restrict a folder, reverse
+/// that, and query whether it's currently locked.
+pub trait LockBackend {
+    fn lock(path: &Path) -> Result<(), LockerError>;
+    fn unlock(path: &Path) -> Result<(), LockerError>;
+    fn is_locked(path: &Path) -> Result<bool, LockerError>;
+}
+
+#[cfg(windows)]
+pub use windows_backend::WindowsLockBackend as DefaultLockBackend;
+
+#[cfg(unix)]
+pub use unix_backend::UnixLockBackend as DefaultLockBackend;
+
+#[cfg(windows)]
+mod windows_backend {
+    use super::LockBackend;
+    use crate::error::LockerError;
+    use crate::file_manager::FileManager;
+    use std::os::windows::fs::MetadataExt;
+    use std::path::Path;
+    use winapi::um::winnt::FILE_ATTRIBUTE_SYSTEM;
+
+    /// Dispatches straight to the existing [`FileManager`] primitives
+    /// (attributes + deny-delete ACL) that already implement Windows
+    /// folder locking.
+    pub struct WindowsLockBackend;
+
+    impl LockBackend for WindowsLockBackend {
+        fn lock(path: &Path) -> Result<(), LockerError> {
+            let path_str = path.to_str().ok_or(LockerError::InvalidFolderName)?;
+            FileManager::set_folder_attributes(path_str)?;
+            FileManager::prevent_folder_deletion(path_str)
+        }
+
+        fn unlock(path: &Path) -> Result<(), LockerError> {
+            let path_str = path.to_str().ok_or(LockerError::InvalidFolderName)?;
+            FileManager::remove_folder_attributes(path_str)
+        }
+
+        fn is_locked(path: &Path) -> Result<bool, LockerError> {
+            let attributes = std::fs::metadata(path)
+                .map_err(|e| LockerError::FileOperationFailed {
+                    operation: "stat".to_string(),
+                    path: path.to_path_buf(),
+                    error: e.to_string(),
+                })?
+                .file_attributes();
+            Ok(attributes & FILE_ATTRIBUTE_SYSTEM != 0)
+        }
+    }
+}
+
+#[cfg(unix)]
+mod unix_backend {
+    use super::{AccessMode, LockBackend};
+    use crate::error::LockerError;
+    use crate::permission_manager::PermissionManager;
+    use std::ffi::CString;
+    use std::path::Path;
+
+    /// Delegates to [`PermissionManager`]'s Unix mode-bit backend, so
+    /// folder locking has a single implementation instead of a second one
+    /// that drifts from it.
+    pub struct UnixLockBackend;
+
+    impl LockBackend for UnixLockBackend {
+        fn lock(path: &Path) -> Result<(), LockerError> {
+            PermissionManager::set_attributes(path).map_err(|e| LockerError::FileOperationFailed {
+                operation: "set permissions on".to_string(),
+                path: path.to_path_buf(),
+                error: e.to_string(),
+            })
+        }
+
+        fn unlock(path: &Path) -> Result<(), LockerError> {
+            PermissionManager::remove_attributes(path).map_err(|e| {
+                LockerError::FileOperationFailed {
+                    operation: "set permissions on".to_string(),
+                    path: path.to_path_buf(),
+                    error: e.to_string(),
+                }
+            })
+        }
+
+        fn is_locked(path: &Path) -> Result<bool, LockerError> {
+            Ok(!probe_access(path, AccessMode::READ))
+        }
+    }
+
+    /// Probes `path` for the given [`AccessMode`] via the platform
+    /// `access()` call, without actually opening it.
+    pub fn probe_access(path: &Path, mode: AccessMode) -> bool {
+        let Some(path_str) = path.to_str() else {
+            return false;
+        };
+        let Ok(c_path) = CString::new(path_str) else {
+            return false;
+        };
+
+        let mut flags = 0;
+        if mode.contains(AccessMode::READ) {
+            flags |= libc::R_OK;
+        }
+        if mode.contains(AccessMode::WRITE) {
+            flags |= libc::W_OK;
+        }
+        if mode.contains(AccessMode::EXECUTE) {
+            flags |= libc::X_OK;
+        }
+        if flags == 0 {
+            flags = libc::F_OK;
+        }
+
+        unsafe { libc::access(c_path.as_ptr(), flags) == 0 }
+    }
+}
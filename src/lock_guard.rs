@@ -0,0 +1,281 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::LockerError;
+
+/// Name of the advisory lock file created alongside the target folder.
+const LOCK_FILE_NAME: &str = ".locker.lock";
+
+/// How long a lock record may sit unrefreshed before it is considered
+/// abandoned and eligible for reclamation.
+const DEFAULT_STALE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// The level at which a folder is locked.
+///
+/// Multiple `Shared` holders may coexist (e.g. concurrent status checks),
+/// but an `Exclusive` holder requires that no other holder, of either
+/// level, be present.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LockLevel {
+    Shared,
+    Exclusive,
+}
+
+/// The on-disk record written into the advisory lock file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LockRecord {
+    hostname: String,
+    process_id: u32,
+    date: DateTime<Utc>,
+    exclusive: bool,
+}
+
+impl LockRecord {
+    fn new(level: LockLevel) -> Self {
+        Self {
+            hostname: hostname(),
+            process_id: std::process::id(),
+            date: Utc::now(),
+            exclusive: level == LockLevel::Exclusive,
+        }
+    }
+
+    /// Whether this record is old enough, or its owning process dead
+    /// enough, to be safely reclaimed by a new lock attempt.
+    fn is_stale(&self, ttl: Duration) -> bool {
+        if !is_process_alive(self.process_id) {
+            return true;
+        }
+        let age = Utc::now().signed_duration_since(self.date);
+        age.to_std().map(|age| age > ttl).unwrap_or(true)
+    }
+}
+
+/// An RAII guard over an advisory lock file next to a locked folder.
+///
+/// The lock file is created on [`LockGuard::acquire`] and removed when the
+/// guard is dropped, so a process that panics still releases the lock as
+/// its stack unwinds. A killed or crashed process never runs `Drop`; its
+/// stale record can be reclaimed via the TTL/liveness check.
+#[derive(Debug)]
+pub struct LockGuard {
+    lock_path: PathBuf,
+    level: LockLevel,
+    /// Exact bytes this guard wrote to `lock_path`, so `Drop` only removes
+    /// the file if it still holds what we wrote rather than a record a
+    /// different process legitimately created after reclaiming a stale one.
+    token: String,
+}
+
+impl LockGuard {
+    /// Acquire an advisory lock for `folder`, creating `.locker.lock` next
+    /// to it. Fails with [`LockerError::Locked`] if a live, non-stale
+    /// record already owns the lock.
+    pub fn acquire(folder: &Path, level: LockLevel) -> Result<Self, LockerError> {
+        Self::acquire_with_ttl(folder, level, DEFAULT_STALE_TTL)
+    }
+
+    /// Like [`LockGuard::acquire`], but with a configurable staleness TTL.
+    ///
+    /// Both levels create the lock file atomically via `create_new` so two
+    /// concurrent callers can never both observe "no lock file" and both
+    /// believe they acquired it. Shared holders are allowed to coexist, so
+    /// a `Shared` acquisition against an existing non-stale `Shared` record
+    /// overwrites it instead of failing; against an `Exclusive` record it's
+    /// treated exactly like another exclusive acquisition would be.
+    pub fn acquire_with_ttl(
+        folder: &Path,
+        level: LockLevel,
+        ttl: Duration,
+    ) -> Result<Self, LockerError> {
+        let lock_path = lock_file_path(folder);
+        let serialized = serde_json::to_string(&LockRecord::new(level)).map_err(|e| {
+            LockerError::FileOperationFailed {
+                operation: "serialize".to_string(),
+                path: lock_path.clone(),
+                error: e.to_string(),
+            }
+        })?;
+
+        loop {
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&lock_path)
+            {
+                Ok(mut file) => {
+                    file.write_all(serialized.as_bytes()).map_err(|e| {
+                        LockerError::FileOperationFailed {
+                            operation: "create lock file".to_string(),
+                            path: lock_path.clone(),
+                            error: e.to_string(),
+                        }
+                    })?;
+                    return Ok(Self {
+                        lock_path,
+                        level,
+                        token: serialized,
+                    });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    match read_lock_record(&lock_path)? {
+                        Some(existing) if level == LockLevel::Shared && !existing.exclusive => {
+                            // Another Shared holder already owns the
+                            // record; Shared holders are meant to coexist,
+                            // so join it rather than treating this as a
+                            // conflict.
+                            fs::write(&lock_path, &serialized).map_err(|e| {
+                                LockerError::FileOperationFailed {
+                                    operation: "create lock file".to_string(),
+                                    path: lock_path.clone(),
+                                    error: e.to_string(),
+                                }
+                            })?;
+                            return Ok(Self {
+                                lock_path,
+                                level,
+                                token: serialized,
+                            });
+                        }
+                        Some(existing) if !existing.is_stale(ttl) => {
+                            return Err(LockerError::Locked {
+                                hostname: existing.hostname,
+                                process_id: existing.process_id,
+                                date: existing.date.to_rfc3339(),
+                            });
+                        }
+                        _ => {
+                            // Stale record (or one that vanished between our
+                            // failed create and this read): reclaim it and
+                            // retry the atomic create. A record that can't be
+                            // read or parsed is reported as an error above.
+                            let _ = fs::remove_file(&lock_path);
+                            continue;
+                        }
+                    }
+                }
+                Err(e) => {
+                    return Err(LockerError::FileOperationFailed {
+                        operation: "create lock file".to_string(),
+                        path: lock_path.clone(),
+                        error: e.to_string(),
+                    })
+                }
+            }
+        }
+    }
+
+    pub fn level(&self) -> LockLevel {
+        self.level
+    }
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        if fs::read_to_string(&self.lock_path)
+            .map(|contents| contents == self.token)
+            .unwrap_or(false)
+        {
+            let _ = fs::remove_file(&self.lock_path);
+        }
+    }
+}
+
+fn lock_file_path(folder: &Path) -> PathBuf {
+    folder
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(LOCK_FILE_NAME)
+}
+
+fn read_lock_record(lock_path: &Path) -> Result<Option<LockRecord>, LockerError> {
+    match fs::read_to_string(lock_path) {
+        Ok(contents) => {
+            let record =
+                serde_json::from_str(&contents).map_err(|e| LockerError::FileOperationFailed {
+                    operation: "parse".to_string(),
+                    path: lock_path.to_path_buf(),
+                    error: e.to_string(),
+                })?;
+            Ok(Some(record))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(LockerError::FileOperationFailed {
+            operation: "read".to_string(),
+            path: lock_path.to_path_buf(),
+            error: e.to_string(),
+        }),
+    }
+}
+
+fn hostname() -> String {
+    #[cfg(windows)]
+    {
+        std::env::var("COMPUTERNAME").unwrap_or_else(|_| "unknown-host".to_string())
+    }
+    #[cfg(not(windows))]
+    {
+        std::env::var("HOSTNAME").unwrap_or_else(|_| "unknown-host".to_string())
+    }
+}
+
+#[cfg(unix)]
+fn is_process_alive(pid: u32) -> bool {
+    unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
+}
+
+#[cfg(windows)]
+fn is_process_alive(pid: u32) -> bool {
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::processthreadsapi::OpenProcess;
+    use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle.is_null() {
+            false
+        } else {
+            CloseHandle(handle);
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record_aged_by(age: Duration) -> LockRecord {
+        LockRecord {
+            hostname: "test-host".to_string(),
+            process_id: std::process::id(),
+            date: Utc::now() - chrono::Duration::from_std(age).unwrap(),
+            exclusive: true,
+        }
+    }
+
+    #[test]
+    fn fresh_record_from_live_process_is_not_stale() {
+        let record = record_aged_by(Duration::from_secs(1));
+        assert!(!record.is_stale(DEFAULT_STALE_TTL));
+    }
+
+    #[test]
+    fn record_older_than_ttl_is_stale() {
+        let record = record_aged_by(DEFAULT_STALE_TTL + Duration::from_secs(1));
+        assert!(record.is_stale(DEFAULT_STALE_TTL));
+    }
+
+    #[test]
+    fn record_from_dead_process_is_stale_regardless_of_age() {
+        let mut record = record_aged_by(Duration::from_secs(1));
+        // Far beyond any real PID space, so this process is never "alive".
+        record.process_id = 999_999_999;
+        assert!(record.is_stale(DEFAULT_STALE_TTL));
+    }
+}
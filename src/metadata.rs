@@ -1,59 +1,149 @@
-use std::{
-    fs::File,
-    io::{Read, Write},
-    path::Path,
-};
+use std::{fs, path::Path};
 
+use chrono::{DateTime, Utc};
+#[cfg(windows)]
 use colored::Colorize;
+#[cfg(windows)]
 use log::error;
+use log::info;
+use serde::{Deserialize, Serialize};
 
+use crate::atomic_file::{read_atomic, write_atomic};
+use crate::config::LockerConfig;
 use crate::error::LockerError;
+#[cfg(windows)]
 use crate::file_manager::FileManager;
-pub const METADATA_FILE: &str = ".locker_metadata";
 
-pub fn write_metadata(hidden_path: &Path, hashed_password: &str) -> Result<(), LockerError> {
-    let metadata_path = hidden_path.join(METADATA_FILE);
-    let mut file = File::create(&metadata_path).map_err(|e| LockerError::FileOperationFailed {
-        operation: "create".to_string(),
-        path: metadata_path.clone(),
-        error: e.to_string(),
-    })?;
-    file.write_all(hashed_password.as_bytes())
-        .map_err(|e| LockerError::FileOperationFailed {
-            operation: "write".to_string(),
-            path: metadata_path.clone(),
+/// Current on-disk format of [`LockerMetadata`]. Bump this whenever the
+/// struct's shape changes so `read_metadata` knows how to migrate older
+/// records.
+const FORMAT_VERSION: u32 = 1;
+
+/// Structured, versioned record persisted alongside a locked folder.
+///
+/// Replaces the historical bare-bcrypt-string metadata file so the format
+/// can evolve (new hash algorithms, richer provenance) without breaking
+/// folders locked by older versions of the tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockerMetadata {
+    pub format_version: u32,
+    pub hash: String,
+    pub algorithm: String,
+    pub created_at: DateTime<Utc>,
+    /// The folder's name before it was renamed/hidden at lock time.
+    pub original_name: String,
+    /// `user@hostname` of whoever locked the folder.
+    pub locked_by: String,
+    /// Original Unix owner/group/other mode bits, captured before the
+    /// lock restricted them, so unlock can restore them exactly.
+    /// Always `None` on Windows.
+    pub original_mode: Option<u32>,
+}
+
+impl LockerMetadata {
+    fn new(hashed_password: &str, original_name: &str, original_mode: Option<u32>) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            hash: hashed_password.to_string(),
+            algorithm: "bcrypt".to_string(),
+            created_at: Utc::now(),
+            original_name: original_name.to_string(),
+            locked_by: identity(),
+            original_mode,
+        }
+    }
+
+    /// Upgrade a legacy metadata file that held nothing but a raw bcrypt
+    /// hash. `original_name` is derived from the current (hidden) path
+    /// since the true pre-rename name isn't recoverable from that format.
+    fn from_legacy(raw_hash: &str, original_name: &str) -> Self {
+        Self::new(raw_hash, original_name, None)
+    }
+}
+
+pub fn write_metadata(
+    hidden_path: &Path,
+    hashed_password: &str,
+    original_name: &str,
+    original_mode: Option<u32>,
+    config: &LockerConfig,
+) -> Result<(), LockerError> {
+    let metadata = LockerMetadata::new(hashed_password, original_name, original_mode);
+    write_metadata_struct(hidden_path, &metadata, config)
+}
+
+fn write_metadata_struct(
+    hidden_path: &Path,
+    metadata: &LockerMetadata,
+    config: &LockerConfig,
+) -> Result<(), LockerError> {
+    let metadata_dir = config.metadata_dir(hidden_path);
+    let serialized = serde_json::to_string(metadata).map_err(|e| {
+        LockerError::FileOperationFailed {
+            operation: "serialize".to_string(),
+            path: metadata_dir.join(&config.metadata_file),
             error: e.to_string(),
-        })?;
-    if let Err(e) = FileManager::set_file_attributes(&metadata_path) {
-        error!("Failed to set file attributes: {}", e);
-        println!("{}", "Failed to set file attributes.".red());
-    };
+        }
+    })?;
+    write_atomic(&metadata_dir, &config.metadata_file, &serialized)?;
+
+    #[cfg(windows)]
+    {
+        let metadata_path = metadata_dir.join(&config.metadata_file);
+        if let Err(e) = FileManager::set_file_attributes(&metadata_path) {
+            error!("Failed to set file attributes: {}", e);
+            println!("{}", "Failed to set file attributes.".red());
+        }
+    }
+
     Ok(())
 }
 
-pub fn read_metadata(hidden_path: &Path) -> Result<String, LockerError> {
-    let metadata_path = hidden_path.join(METADATA_FILE);
-    let mut stored_password = String::new();
-    File::open(&metadata_path)
-        .and_then(|mut file| file.read_to_string(&mut stored_password))
-        .map_err(|e| LockerError::FileOperationFailed {
-            operation: "read".to_string(),
-            path: metadata_path.clone(),
-            error: e.to_string(),
-        })?;
-    Ok(stored_password)
+/// Read the metadata record for a locked folder, transparently upgrading
+/// a legacy bare-bcrypt-hash file to the structured format in place.
+pub fn read_metadata(hidden_path: &Path, config: &LockerConfig) -> Result<LockerMetadata, LockerError> {
+    let metadata_dir = config.metadata_dir(hidden_path);
+    let contents = read_atomic(&metadata_dir, &config.metadata_file)?;
+
+    match serde_json::from_str::<LockerMetadata>(&contents) {
+        Ok(metadata) => Ok(metadata),
+        Err(_) => {
+            let metadata_path = metadata_dir.join(&config.metadata_file);
+            info!("Upgrading legacy metadata file: {:?}", metadata_path);
+            let original_name = hidden_path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| name.trim_start_matches('.').to_string())
+                .ok_or(LockerError::InvalidFolderName)?;
+            let metadata = LockerMetadata::from_legacy(contents.trim(), &original_name);
+            write_metadata_struct(hidden_path, &metadata, config)?;
+            Ok(metadata)
+        }
+    }
 }
 
-pub fn remove_metadata(hidden_path: &Path) -> Result<(), LockerError> {
+pub fn remove_metadata(hidden_path: &Path, config: &LockerConfig) -> Result<(), LockerError> {
+    #[cfg(windows)]
     if let Err(e) = FileManager::remove_folder_attributes(hidden_path.to_str().unwrap()) {
         error!("Failed to remove folder attributes: {}", e);
         println!("{}", "Failed to remove folder attributes.".red());
-    };
-    let metadata_path = hidden_path.join(METADATA_FILE);
-    std::fs::remove_file(metadata_path).map_err(|e| LockerError::FileOperationFailed {
+    }
+
+    let metadata_path = config.metadata_dir(hidden_path).join(&config.metadata_file);
+    fs::remove_file(&metadata_path).map_err(|e| LockerError::FileOperationFailed {
         operation: "remove".to_string(),
-        path: hidden_path.join(METADATA_FILE).clone(),
+        path: metadata_path,
         error: e.to_string(),
     })?;
     Ok(())
 }
+
+fn identity() -> String {
+    let user = std::env::var("USERNAME")
+        .or_else(|_| std::env::var("USER"))
+        .unwrap_or_else(|_| "unknown-user".to_string());
+    let host = std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "unknown-host".to_string());
+    format!("{}@{}", user, host)
+}
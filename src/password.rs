@@ -1,4 +1,4 @@
-use bcrypt::{hash, verify, DEFAULT_COST};
+use bcrypt::{hash, verify};
 use dialoguer::{theme::ColorfulTheme, Password};
 
 use crate::error::LockerError;
@@ -16,8 +16,8 @@ pub fn get_password() -> Result<String, LockerError> {
     Ok(password)
 }
 
-pub fn hash_password(password: &str) -> Result<String, LockerError> {
-    hash(password, DEFAULT_COST).map_err(|_| LockerError::PasswordOperationFailed {
+pub fn hash_password(password: &str, cost: u32) -> Result<String, LockerError> {
+    hash(password, cost).map_err(|_| LockerError::PasswordOperationFailed {
         operation: "hash".to_string(),
         reason: "Encryption error".to_string(),
     })
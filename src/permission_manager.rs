@@ -1,95 +1,147 @@
 use std::io;
 use std::path::Path;
-use std::process::Command;
+
+/// Platform-specific hook for hiding/restricting a locked folder.
+///
+/// `PermissionManager` dispatches to whichever backend matches the target
+/// platform; each backend owns the OS-specific mechanism for making a
+/// folder inaccessible (and later restoring it).
+trait AttributeBackend {
+    fn set_attributes(path: &Path) -> io::Result<()>;
+    fn remove_attributes(path: &Path) -> io::Result<()>;
+    /// Clears just the permission/ACL bits that make a folder exposed to
+    /// other principals, leaving it visible and usable by its owner —
+    /// unlike `set_attributes`, which hides the folder and locks out
+    /// everyone including the owner.
+    fn harden(path: &Path) -> io::Result<()>;
+}
 
 pub struct PermissionManager;
 
 impl PermissionManager {
-    /// Sets specific attributes on a file or folder to restrict access and make it hidden.
-    ///
-    /// This function modifies the access control list (ACL) of the specified path
-    /// to enhance security, restrict access, and make the folder hidden. Here's what each argument does:
-    ///
-    /// - `/inheritance:d`: Disables inheritance from parent objects.
-    /// - `/grant:r`: Grants read-only access.
-    /// - `Administrators:(OI)(CI)F`: Gives full control to Administrators, applying to this object and child objects.
-    /// - `/remove *S-1-1-0`: Removes permissions for the "Everyone" group (SID S-1-1-0).
-    /// - `/deny *S-1-1-0:(DE,DC)`: Denies delete and change permissions to the "Everyone" group.
-    ///
-    /// # Arguments
-    ///
-    /// * `path` - A path-like object representing the file or folder to modify.
-    ///
-    /// # Returns
-    ///
-    /// * `io::Result<()>` - Ok if successful, Err with io::Error if failed.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if the path is invalid or if the `icacls` or `attrib` commands fail.
+    /// Restricts access to `path`, using whichever backend matches the
+    /// current platform. The Windows backend also hides it; on Unix,
+    /// hiding comes from the caller's dot-prefixed folder name.
     pub fn set_attributes<P: AsRef<Path>>(path: P) -> io::Result<()> {
-        let path_str = path
-            .as_ref()
-            .to_str()
-            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid path"))?;
-
-        // Set ACL permissions
-        Self::icacls(&[
-            path_str,
-            "/inheritance:d",
-            "/grant:r",
-            "Administrators:(OI)(CI)F",
-            "/remove",
-            "*S-1-1-0",
-            "/deny",
-            "*S-1-1-0:(DE,DC)",
-        ])?;
-
-        // Make the folder hidden
-        Self::attrib(&["+H", path_str])
+        Backend::set_attributes(path.as_ref())
+    }
+
+    /// Reverses [`PermissionManager::set_attributes`], restoring default
+    /// access (and, on Windows, visibility).
+    pub fn remove_attributes<P: AsRef<Path>>(path: P) -> io::Result<()> {
+        Backend::remove_attributes(path.as_ref())
+    }
+
+    /// Hardens `path` against the exposure `audit` flags it for, without
+    /// locking it the way [`PermissionManager::set_attributes`] would.
+    pub fn harden<P: AsRef<Path>>(path: P) -> io::Result<()> {
+        Backend::harden(path.as_ref())
+    }
+
+    /// Runs `icacls` with the given arguments and returns its stdout, so
+    /// callers that need to inspect rather than mutate an ACL (e.g. the
+    /// `audit` subsystem) can reuse the same tool invocation.
+    #[cfg(windows)]
+    pub fn icacls(args: &[&str]) -> io::Result<String> {
+        let output = std::process::Command::new("icacls").args(args).output()?;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                String::from_utf8_lossy(&output.stderr),
+            ))
+        }
+    }
+}
+
+#[cfg(windows)]
+use windows_backend::WindowsBackend as Backend;
+
+#[cfg(unix)]
+use unix_backend::UnixBackend as Backend;
+
+#[cfg(windows)]
+mod windows_backend {
+    use super::AttributeBackend;
+    use std::io;
+    use std::path::Path;
+    use std::process::Command;
+
+    pub(super) struct WindowsBackend;
+
+    impl AttributeBackend for WindowsBackend {
+        /// Sets specific attributes on a file or folder to restrict access and make it hidden.
+        ///
+        /// This function modifies the access control list (ACL) of the specified path
+        /// to enhance security, restrict access, and make the folder hidden. Here's what each argument does:
+        ///
+        /// - `/inheritance:d`: Disables inheritance from parent objects.
+        /// - `/grant:r`: Grants read-only access.
+        /// - `Administrators:(OI)(CI)F`: Gives full control to Administrators, applying to this object and child objects.
+        /// - `/remove *S-1-1-0`: Removes permissions for the "Everyone" group (SID S-1-1-0).
+        /// - `/deny *S-1-1-0:(DE,DC)`: Denies delete and change permissions to the "Everyone" group.
+        fn set_attributes(path: &Path) -> io::Result<()> {
+            reject_reparse_point(path)?;
+
+            let path_str = path
+                .to_str()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid path"))?;
+
+            // Set ACL permissions
+            icacls(&[
+                path_str,
+                "/inheritance:d",
+                "/grant:r",
+                "Administrators:(OI)(CI)F",
+                "/remove",
+                "*S-1-1-0",
+                "/deny",
+                "*S-1-1-0:(DE,DC)",
+            ])?;
+
+            // Make the folder hidden
+            attrib(&["+H", path_str])
+        }
+
+        /// Removes custom attributes, resets permissions, and unhides a file or folder.
+        ///
+        /// This function uses the `icacls` command to reset the access control lists (ACLs)
+        /// on the specified file or folder to their inherited values, and removes the hidden attribute.
+        fn remove_attributes(path: &Path) -> io::Result<()> {
+            reject_reparse_point(path)?;
+
+            let path_str = path
+                .to_str()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid path"))?;
+            icacls(&[path_str, "/reset", "/T"])?;
+            attrib(&["-H", path_str])
+        }
+
+        /// Removes the "Everyone" ACE `audit` flagged, leaving every other
+        /// grant (including the owner's) and the folder's visibility
+        /// untouched.
+        fn harden(path: &Path) -> io::Result<()> {
+            reject_reparse_point(path)?;
+
+            let path_str = path
+                .to_str()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid path"))?;
+            icacls(&[path_str, "/remove", "*S-1-1-0"])
+        }
     }
 
-    /// Removes custom attributes, resets permissions, and unhides a file or folder.
-    ///
-    /// This function uses the `icacls` command to reset the access control lists (ACLs)
-    /// on the specified file or folder to their inherited values, and removes the hidden attribute.
-    /// It effectively removes any custom permissions that were previously set and makes the folder visible.
-    ///
-    /// # Arguments
-    ///
-    /// * `name` - A string slice that holds the path of the file or folder.
-    ///
-    /// # Returns
-    ///
-    /// * `io::Result<()>` - Ok if the operation was successful, Err otherwise.
-    ///
-    /// # How it works
-    ///
-    /// 1. Calls `Self::icacls` to reset ACLs
-    /// 2. Calls `Self::attrib` to remove the hidden attribute
-    ///
-    /// This effectively undoes the changes made by `set_attributes`, restoring
-    /// default permissions and allowing normal access to the file or folder.
-    pub fn remove_attributes(name: &str) -> io::Result<()> {
-        Self::icacls(&[name, "/reset", "/T"])?;
-        Self::attrib(&["-H", name])
+    /// Delegates to [`crate::file_manager`]'s reparse-point check,
+    /// translating its `LockerError` into the `io::Result` this backend's
+    /// trait uses. Without this, `icacls`/`attrib` would silently mutate
+    /// whatever a junction/symlink under `path` actually points to.
+    fn reject_reparse_point(path: &Path) -> io::Result<()> {
+        crate::file_manager::reject_reparse_point(path)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))
     }
 
     /// Executes the `icacls` command with the given arguments.
-    ///
-    /// This function is used to modify discretionary access control lists (DACLs) on files and folders
-    /// in Windows systems. It runs the `icacls` (Improved Command Access Control Lists) command-line tool
-    /// with the provided arguments.
-    ///
-    /// # Arguments
-    ///
-    /// * `args` - A slice of string slices containing the arguments to pass to `icacls`.
-    ///
-    /// # Returns
-    ///
-    /// * `Ok(())` if the command executes successfully.
-    /// * `Err(io::Error)` if the command fails, containing the error message from stderr.
-    fn icacls(args: &[&str]) -> io::Result<()> {
+    pub(super) fn icacls(args: &[&str]) -> io::Result<()> {
         let output = Command::new("icacls").args(args).output()?;
         if output.status.success() {
             Ok(())
@@ -102,18 +154,6 @@ impl PermissionManager {
     }
 
     /// Executes the `attrib` command with the given arguments.
-    ///
-    /// This function is used to change attributes of files or folders in Windows systems.
-    /// It runs the `attrib` command-line tool with the provided arguments.
-    ///
-    /// # Arguments
-    ///
-    /// * `args` - A slice of string slices containing the arguments to pass to `attrib`.
-    ///
-    /// # Returns
-    ///
-    /// * `Ok(())` if the command executes successfully.
-    /// * `Err(io::Error)` if the command fails, containing the error message from stderr.
     fn attrib(args: &[&str]) -> io::Result<()> {
         let output = Command::new("attrib").args(args).output()?;
         if output.status.success() {
@@ -126,3 +166,62 @@ impl PermissionManager {
         }
     }
 }
+
+#[cfg(unix)]
+mod unix_backend {
+    use super::AttributeBackend;
+    use std::fs;
+    use std::io;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+
+    /// Mode applied while a folder is locked: group and other get no
+    /// access at all; the owner keeps bare traverse (`x`) rights so
+    /// `read_metadata` can still open the metadata file by name during
+    /// unlock. Without owner execute, the owner's own unlock would fail
+    /// with `EACCES` trying to open a file inside a directory they can't
+    /// traverse — only root (which bypasses DAC checks) could recover it.
+    const LOCKED_MODE: u32 = 0o100;
+    /// Mode restored by a bare `remove_attributes` call when the original
+    /// mode wasn't captured (e.g. a legacy metadata record).
+    const DEFAULT_UNLOCKED_MODE: u32 = 0o700;
+
+    pub(super) struct UnixBackend;
+
+    impl AttributeBackend for UnixBackend {
+        /// Drops the directory mode to owner-traverse-only, denying
+        /// listing and writing to everyone including the owner. Hiding is
+        /// achieved by the caller's dot-prefix naming convention, not by a
+        /// separate attribute here.
+        fn set_attributes(path: &Path) -> io::Result<()> {
+            fs::set_permissions(path, fs::Permissions::from_mode(LOCKED_MODE))
+        }
+
+        fn remove_attributes(path: &Path) -> io::Result<()> {
+            fs::set_permissions(path, fs::Permissions::from_mode(DEFAULT_UNLOCKED_MODE))
+        }
+
+        /// Clears group-write, other-write, and other-read from the
+        /// current mode bits, leaving the owner's bits (and thus their
+        /// access) exactly as they were.
+        fn harden(path: &Path) -> io::Result<()> {
+            let mode = fs::metadata(path)?.permissions().mode() & 0o777;
+            let hardened = mode & !0o026;
+            fs::set_permissions(path, fs::Permissions::from_mode(hardened))
+        }
+    }
+
+    impl super::PermissionManager {
+        /// Reads the current owner/group/other mode bits of `path`, so
+        /// they can be saved in metadata and restored exactly on unlock.
+        pub fn capture_mode<P: AsRef<Path>>(path: P) -> io::Result<u32> {
+            Ok(fs::metadata(path)?.permissions().mode() & 0o777)
+        }
+
+        /// Restores previously-captured mode bits on unlock, in place of
+        /// the generic [`PermissionManager::remove_attributes`] default.
+        pub fn restore_mode<P: AsRef<Path>>(path: P, mode: u32) -> io::Result<()> {
+            fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        }
+    }
+}
@@ -1,36 +0,0 @@
-use std::path::Path;
-use winapi::um::fileapi::SetFileAttributesW;
-use winapi::um::winnt::{FILE_ATTRIBUTE_HIDDEN, FILE_ATTRIBUTE_READONLY, FILE_ATTRIBUTE_SYSTEM};
-
-pub fn set_file_attributes(path: &Path) {
-    use std::os::windows::ffi::OsStrExt;
-    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
-    unsafe {
-        SetFileAttributesW(wide.as_ptr(), FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM);
-    }
-}
-
-pub fn set_folder_attributes(name: &str) {
-    use std::os::windows::ffi::OsStrExt;
-    let wide: Vec<u16> = std::ffi::OsStr::new(name)
-        .encode_wide()
-        .chain(Some(0))
-        .collect();
-    unsafe {
-        SetFileAttributesW(
-            wide.as_ptr(),
-            FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM | FILE_ATTRIBUTE_READONLY,
-        );
-    }
-}
-
-pub fn remove_folder_attributes(name: &str) {
-    use std::os::windows::ffi::OsStrExt;
-    let wide: Vec<u16> = std::ffi::OsStr::new(name)
-        .encode_wide()
-        .chain(Some(0))
-        .collect();
-    unsafe {
-        SetFileAttributesW(wide.as_ptr(), 0);
-    }
-}